@@ -1,4 +1,5 @@
-use anyhow::{bail, Context as _, Result};
+use anyhow::{Context as _, Result};
+use pnpm_extra::{edit_workspace, read_workspace};
 
 #[derive(clap::Subcommand)]
 pub(crate) enum Args {
@@ -14,34 +15,18 @@ pub(crate) enum Args {
     },
 }
 
-fn read_workspace() -> Result<serde_yaml::Mapping> {
-    let data = std::fs::read("pnpm-workspace.yaml").context("reading pnpm-workspace.yaml");
-    let workspace = serde_yaml::from_slice::<serde_yaml::Value>(&data?)
-        .context("parsing pnpm-workspace.yaml")?;
-    let workspace = match workspace {
-        serde_yaml::Value::Mapping(map) => map,
-        _ => bail!("pnpm-workspace.yaml content is not a mapping?"),
-    };
-    Ok(workspace)
-}
-
 pub(crate) fn run(args: Args) -> Result<()> {
     match args {
         Args::Add { name, catalog } => {
-            let mut workspace = read_workspace()?;
-            let catalog = match catalog {
-                None => workspace.entry("catalog".into()),
+            let workspace = read_workspace()?;
+            let existing = match &catalog {
+                None => workspace.get("catalog"),
                 Some(catalog) => workspace
-                    .entry("catalogs".into())
-                    .or_insert_with(|| serde_yaml::Value::Mapping(Default::default()))
-                    .as_mapping_mut()
-                    .context("catalogs is not a mapping")?
-                    .entry(catalog.into()),
+                    .get("catalogs")
+                    .and_then(|catalogs| catalogs.get(catalog)),
             }
-            .or_insert_with(|| serde_yaml::Value::Mapping(Default::default()))
-            .as_mapping_mut()
-            .context("catalog is not a mapping")?;
-            if let Some(version) = catalog.get(&name) {
+            .and_then(|catalog| catalog.get(&name));
+            if let Some(version) = existing {
                 println!("{} is already in catalog with version {:?}", name, version);
                 return Ok(());
             }
@@ -60,23 +45,8 @@ pub(crate) fn run(args: Args) -> Result<()> {
                 .context("latest version not found")?;
             println!("found {}@{}", name, version);
 
-            catalog.insert(name.into(), format!("^{}", version).into());
-
-            // This will write somewhat ugly yaml, no line separators and single-quoted strings.
-            std::fs::write(
-                "pnpm-workspace.yaml",
-                serde_yaml::to_string(&workspace).context("serializing pnpm-workspace.yaml")?,
-            )
-            .context("writing pnpm-workspace.yaml")?;
-            // So run prettier on it:
-            std::process::Command::new("pnpm")
-                .arg("exec")
-                .arg("--")
-                .arg("prettier")
-                .arg("--write")
-                .arg("pnpm-workspace.yaml")
-                .status()
-                .context("running prettier")?;
+            edit_workspace(catalog.as_deref(), &name, &format!("^{}", version))
+                .context("editing pnpm-workspace.yaml")?;
 
             Ok(())
         }
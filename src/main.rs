@@ -8,26 +8,96 @@ use std::path::PathBuf;
 enum Args {
     /// better pnpm why, modelled on cargo tree -i
     Tree {
-        #[clap(name = "name")]
+        #[clap(name = "name", required_unless_present = "duplicates")]
         /// Package name to show the tree for
-        name: String,
+        name: Option<String>,
 
         #[clap(short, long, default_value = ".")]
         /// Workspace directory
         dir: PathBuf,
+
+        #[clap(long, overrides_with = "no_invert", default_value_t = true)]
+        /// Show dependents of `name` rather than its dependencies (default)
+        invert: bool,
+
+        #[clap(long = "no-invert")]
+        /// Show dependencies of `name` rather than its dependents
+        no_invert: bool,
+
+        #[clap(long, conflicts_with = "name")]
+        /// List packages resolved to more than one version, modelled on `cargo tree -d`
+        duplicates: bool,
+
+        #[clap(long, value_enum, default_value_t = Format::Text)]
+        /// Output format
+        format: Format,
+    },
+
+    /// Audit pnpm-lock.yaml snapshots for missing or malformed integrity hashes
+    Verify {
+        #[clap(short, long, default_value = ".")]
+        /// Workspace directory
+        dir: PathBuf,
+
+        #[clap(long)]
+        /// Resolve and write back missing/malformed integrity hashes instead of just reporting them
+        fix: bool,
     },
 
     #[clap(subcommand)]
     Catalog(catalog::Args),
 }
 
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Format {
+    /// Indented plain text (default)
+    Text,
+    /// Nested JSON structure
+    Json,
+    /// Graphviz digraph
+    Dot,
+}
+
+impl From<Format> for pnpm_extra::Format {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Text => pnpm_extra::Format::Text,
+            Format::Json => pnpm_extra::Format::Json,
+            Format::Dot => pnpm_extra::Format::Dot,
+        }
+    }
+}
+
 mod catalog;
 
 fn main() -> Result<()> {
     match Args::parse() {
-        Args::Tree { name, dir } => {
+        Args::Tree {
+            name,
+            dir,
+            invert,
+            no_invert,
+            duplicates,
+            format,
+        } => {
+            let dir = std::path::absolute(dir)?;
+            if duplicates {
+                pnpm_extra::print_duplicates(&dir)?;
+            } else {
+                let mut stdout = std::io::stdout();
+                pnpm_extra::print_tree_to(
+                    &mut stdout,
+                    format.into(),
+                    &dir,
+                    &name.unwrap(),
+                    invert && !no_invert,
+                )?;
+            }
+            Ok(())
+        }
+        Args::Verify { dir, fix } => {
             let dir = std::path::absolute(dir)?;
-            pnpm_extra::tree::print_tree(&dir, &name)?;
+            pnpm_extra::verify(&dir, fix)?;
             Ok(())
         }
         Args::Catalog(args) => {
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
@@ -21,21 +21,231 @@ pub enum Error {
     /// Error when the pnpm-lock.yaml file cannot be parsed.
     ParseLockfile(#[source] serde_yaml::Error),
 
-    #[error("Unexpected lockfile content")]
-    /// Error when the lockfile content could not be understood.
-    /// Currently, this is only when the snapshot key cannot be split into a package name and
-    /// version.
-    UnexpectedLockfileContent,
+    #[error("invalid dependency path: {0}")]
+    /// Error when a snapshot key (depPath) cannot be parsed by [`DepPath::parse`].
+    InvalidDepPath(String),
+
+    #[error("could not write tree output: {0}")]
+    /// Error when [`print_tree_to`] fails to write to its output writer.
+    WriteOutput(#[source] std::io::Error),
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The parsed form of a pnpm lockfile "depPath", e.g. the key of the `snapshots` map.
+///
+/// Handles the `name@version`, `name@version(peer@version)`, and
+/// `name@version(patch_hash=...)` forms used by lockfileVersion 7 and 9, including scoped package
+/// names (`@scope/name@version`) and multiple peer qualifiers.
+pub struct DepPath {
+    /// The package name, e.g. "foo" or "@scope/foo".
+    pub name: String,
+
+    /// The resolved version, without any peer or patch qualifiers, e.g. "1.2.3".
+    pub version: String,
+
+    /// The peer-dependency qualifiers, e.g. "(react@18.0.0)(zod@3.0.0)", if any.
+    pub peer_suffix: Option<String>,
+
+    /// The patch hash from a `(patch_hash=...)` qualifier, if any.
+    pub patch: Option<String>,
+}
+
+impl DepPath {
+    /// Parse a snapshot key (depPath) into its component parts.
+    ///
+    /// # Errors
+    /// - [`Error::InvalidDepPath`] If the key has no `@` separating a name from a version, or
+    ///   its parenthesized qualifiers are not balanced.
+    pub fn parse(key: &str) -> Result<Self> {
+        let invalid = || Error::InvalidDepPath(key.to_string());
+
+        // For scoped packages ("@scope/name@version"), the name runs from the start through
+        // the "/" up to the *next* "@"; otherwise the name ends at the first "@".
+        let name_end = if let Some(rest) = key.strip_prefix('@') {
+            let slash = rest.find('/').ok_or_else(invalid)?;
+            1 + slash + rest[slash..].find('@').ok_or_else(invalid)?
+        } else {
+            key.find('@').ok_or_else(invalid)?
+        };
+        let name = key[..name_end].to_string();
+        let rest = &key[name_end + 1..];
+
+        // The version runs up to the first unmatched (i.e. depth-0) "(", or to the end.
+        let mut depth = 0u32;
+        let mut version_end = rest.len();
+        for (i, c) in rest.char_indices() {
+            match c {
+                '(' if depth == 0 => {
+                    version_end = i;
+                    break;
+                }
+                '(' => depth += 1,
+                ')' => depth = depth.checked_sub(1).ok_or_else(invalid)?,
+                _ => {}
+            }
+        }
+        let version = rest[..version_end].to_string();
+
+        // The remainder is a sequence of balanced parenthesized groups, e.g.
+        // "(react@18.0.0)(patch_hash=abc)(zod@3.0.0)".
+        let mut peer_suffix = String::new();
+        let mut patch = None;
+        let mut groups = &rest[version_end..];
+        while !groups.is_empty() {
+            if !groups.starts_with('(') {
+                return Err(invalid());
+            }
+            let mut depth = 0u32;
+            let mut end = None;
+            for (i, c) in groups.char_indices() {
+                match c {
+                    '(' => depth += 1,
+                    ')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(i + 1);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let end = end.ok_or_else(invalid)?;
+            let group = &groups[..end];
+            if let Some(hash) = group.strip_prefix("(patch_hash=").and_then(|g| g.strip_suffix(')'))
+            {
+                patch = Some(hash.to_string());
+            } else {
+                peer_suffix.push_str(group);
+            }
+            groups = &groups[end..];
+        }
+
+        Ok(Self {
+            name,
+            version,
+            peer_suffix: (!peer_suffix.is_empty()).then_some(peer_suffix),
+            patch,
+        })
+    }
+
+    /// Parse a snapshot key in the lockfileVersion 6 form: a leading "/" followed by the same
+    /// `name@version(peers)` form used by lockfileVersion 7 and 9, e.g. "/lodash@4.17.21" or
+    /// "/@babel/code-frame@7.24.7(supports-color@5.5.0)".
+    ///
+    /// Unlike [`Self::parse_slashed`], pnpm switched the separator from "/" to "@" when it
+    /// introduced lockfileVersion 6, so only the leading "/" is slash-delimited here.
+    ///
+    /// # Errors
+    /// - [`Error::InvalidDepPath`] If the key does not start with "/", or the remainder cannot be
+    ///   parsed by [`Self::parse`].
+    pub fn parse_v6(key: &str) -> Result<Self> {
+        let invalid = || Error::InvalidDepPath(key.to_string());
+        let rest = key.strip_prefix('/').ok_or_else(invalid)?;
+        Self::parse(rest).map_err(|_| invalid())
+    }
+
+    /// Parse a snapshot key in the slash-delimited form used by lockfileVersion 5, e.g.
+    /// "/foo/1.2.3", "/@scope/foo/1.2.3", or "/bar/4.5.6_peer@7.8.9".
+    ///
+    /// Patches are not modelled by this older lockfile version, so `patch` is always `None`.
+    ///
+    /// # Errors
+    /// - [`Error::InvalidDepPath`] If the key does not start with "/" or has no version segment.
+    pub fn parse_slashed(key: &str) -> Result<Self> {
+        let invalid = || Error::InvalidDepPath(key.to_string());
+
+        let rest = key.strip_prefix('/').ok_or_else(invalid)?;
+        let mut segments = rest.splitn(if rest.starts_with('@') { 3 } else { 2 }, '/');
+        let name = if rest.starts_with('@') {
+            let scope = segments.next().ok_or_else(invalid)?;
+            let package = segments.next().ok_or_else(invalid)?;
+            format!("{scope}/{package}")
+        } else {
+            segments.next().ok_or_else(invalid)?.to_string()
+        };
+        let version_and_suffix = segments.next().ok_or_else(invalid)?;
+
+        // Peer qualifiers are appended directly to the version, e.g. "1.2.3_peer@4.5.6" or
+        // "1.2.3(peer@4.5.6)".
+        let suffix_start = version_and_suffix
+            .find(['_', '('])
+            .unwrap_or(version_and_suffix.len());
+        let version = version_and_suffix[..suffix_start].to_string();
+        let peer_suffix = (suffix_start < version_and_suffix.len())
+            .then(|| version_and_suffix[suffix_start..].to_string());
+
+        Ok(Self {
+            name,
+            version,
+            peer_suffix,
+            patch: None,
+        })
+    }
+
+    /// Reassemble the `version(peer@version)(patch_hash=...)` form used as a [`NodeId::Package`]
+    /// version, matching the version strings recorded against importers' own dependencies.
+    pub fn qualified_version(&self) -> String {
+        let mut version = self.version.clone();
+        if let Some(peer_suffix) = &self.peer_suffix {
+            version.push_str(peer_suffix);
+        }
+        if let Some(patch) = &self.patch {
+            version.push_str("(patch_hash=");
+            version.push_str(patch);
+            version.push(')');
+        }
+        version
+    }
+}
+
+#[derive(Debug)]
 #[non_exhaustive]
-#[serde(tag = "lockfileVersion")]
 /// A subset of the pnpm-lock.yaml file format.
 pub enum Lockfile {
-    #[serde(rename = "9.0")]
-    /// Only supports version 9.0 currently, though apparently versions are backwards compatible?
+    /// Lockfile version 5.x. Snapshot keys are slash-delimited, e.g. "/foo/1.2.3" or
+    /// "/@scope/foo/1.2.3(peer@4.5.6)".
+    V5 {
+        /// See [`Lockfile::V9::importers`].
+        importers: HashMap<String, Importer>,
+
+        /// Packages describe the packages in the store (e.g. from the registry) and their
+        /// resolved dependencies.
+        ///
+        /// The key is a slash-delimited depPath, e.g.: "/foo/1.2.3", "/@scope/foo/1.2.3", or
+        /// "/bar/4.5.6_peer@7.8.9" for peer-qualified entries.
+        snapshots: HashMap<String, Snapshot>,
+    },
+
+    /// Lockfile version 6.x. pnpm switched the depPath separator from "/" to "@" in this
+    /// version, keeping only the leading "/", e.g. "/lodash@4.17.21" or
+    /// "/@babel/code-frame@7.24.7(supports-color@5.5.0)".
+    V6 {
+        /// See [`Lockfile::V9::importers`].
+        importers: HashMap<String, Importer>,
+
+        /// As [`Lockfile::V5::snapshots`], but keyed by the `@`-separated depPath described on
+        /// [`Lockfile::V6`], e.g. "/lodash@4.17.21".
+        snapshots: HashMap<String, Snapshot>,
+    },
+
+    /// Lockfile version 7.x. Drops the leading "/" from snapshot keys, switching to the same
+    /// `name@version(peers)` depPath form used by [`Lockfile::V9`].
+    V7 {
+        /// See [`Lockfile::V9::importers`].
+        importers: HashMap<String, Importer>,
+
+        /// See [`Lockfile::V9::snapshots`].
+        snapshots: HashMap<String, Snapshot>,
+    },
+
+    /// Lockfile version 9.x, though apparently versions are backwards compatible?
     /// https://github.com/orgs/pnpm/discussions/6857
+    ///
+    /// Unlike the older single-section formats, v9 splits resolution from the dependency tree:
+    /// `packages` holds per-package resolution metadata (keyed by bare `name@version`, since
+    /// resolving a tarball doesn't depend on peers), while `snapshots` holds the peer-resolved
+    /// dependency tree (keyed by the full depPath). See [`Lockfile::resolution`].
     V9 {
         /// Importers describe the packages in the workspace and their resolved dependencies.
         ///
@@ -43,6 +253,10 @@ pub enum Lockfile {
         /// "packages/foo", or "." for the workspace root.
         importers: HashMap<String, Importer>,
 
+        /// Per-package resolution metadata, keyed by bare `name@version` (no peer or patch
+        /// qualifiers).
+        packages: HashMap<String, PackageMeta>,
+
         /// Snapshots describe the packages in the store (e.g. from the registry) and their
         /// resolved dependencies.
         ///
@@ -55,6 +269,77 @@ pub enum Lockfile {
     },
 }
 
+impl<'de> serde::Deserialize<'de> for Lockfile {
+    /// Deserializes by major version only (e.g. any "5.*" lockfileVersion becomes [`Self::V5`]),
+    /// since real-world lockfiles carry minor versions other than the one each format was
+    /// introduced with, and `lockfileVersion` may be an unquoted YAML number rather than a string.
+    ///
+    /// `packages` and `snapshots` are deserialized as two independent, optional sections (rather
+    /// than one field aliased to either name) because real lockfileVersion 9 files have *both* at
+    /// once, with different meanings; see [`Lockfile::V9`].
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        struct Raw {
+            #[serde(rename = "lockfileVersion")]
+            lockfile_version: serde_yaml::Value,
+            importers: HashMap<String, Importer>,
+            #[serde(default)]
+            packages: Option<HashMap<String, serde_yaml::Value>>,
+            #[serde(default)]
+            snapshots: Option<HashMap<String, serde_yaml::Value>>,
+        }
+
+        fn convert<T: serde::de::DeserializeOwned, E: serde::de::Error>(
+            raw: Option<HashMap<String, serde_yaml::Value>>,
+            section: &str,
+        ) -> std::result::Result<HashMap<String, T>, E> {
+            raw.ok_or_else(|| E::custom(format!("missing {section} section")))?
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = serde_yaml::from_value(value).map_err(E::custom)?;
+                    Ok((key, value))
+                })
+                .collect()
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        let version = match &raw.lockfile_version {
+            serde_yaml::Value::String(version) => version.clone(),
+            serde_yaml::Value::Number(version) => version.to_string(),
+            other => {
+                return Err(serde::de::Error::custom(format!(
+                    "invalid lockfileVersion: {other:?}"
+                )))
+            }
+        };
+        match version.split('.').next() {
+            Some("5") => Ok(Lockfile::V5 {
+                importers: raw.importers,
+                snapshots: convert(raw.packages, "packages")?,
+            }),
+            Some("6") => Ok(Lockfile::V6 {
+                importers: raw.importers,
+                snapshots: convert(raw.packages, "packages")?,
+            }),
+            Some("7") => Ok(Lockfile::V7 {
+                importers: raw.importers,
+                snapshots: convert(raw.snapshots, "snapshots")?,
+            }),
+            Some("9") => Ok(Lockfile::V9 {
+                importers: raw.importers,
+                packages: convert(raw.packages, "packages")?,
+                snapshots: convert(raw.snapshots, "snapshots")?,
+            }),
+            _ => Err(serde::de::Error::custom(format!(
+                "unsupported lockfileVersion: {version}"
+            ))),
+        }
+    }
+}
+
 impl Lockfile {
     /// Read the content of a pnpm-lock.yaml file.
     ///
@@ -76,6 +361,50 @@ impl Lockfile {
         let result: Self = serde_yaml::from_slice(data).map_err(Error::ParseLockfile)?;
         Ok(result)
     }
+
+    /// The `importers` map, common to all supported lockfile versions.
+    pub fn importers(&self) -> &HashMap<String, Importer> {
+        match self {
+            Lockfile::V5 { importers, .. }
+            | Lockfile::V6 { importers, .. }
+            | Lockfile::V7 { importers, .. }
+            | Lockfile::V9 { importers, .. } => importers,
+        }
+    }
+
+    /// The `snapshots` (or, for lockfileVersion 5/6, `packages`) map, keyed by depPath.
+    pub fn snapshots(&self) -> &HashMap<String, Snapshot> {
+        match self {
+            Lockfile::V5 { snapshots, .. }
+            | Lockfile::V6 { snapshots, .. }
+            | Lockfile::V7 { snapshots, .. }
+            | Lockfile::V9 { snapshots, .. } => snapshots,
+        }
+    }
+
+    /// The depPath parser matching this lockfile version's snapshot key format.
+    pub fn dep_path_parser(&self) -> fn(&str) -> Result<DepPath> {
+        match self {
+            Lockfile::V5 { .. } => DepPath::parse_slashed,
+            Lockfile::V6 { .. } => DepPath::parse_v6,
+            Lockfile::V7 { .. } | Lockfile::V9 { .. } => DepPath::parse,
+        }
+    }
+
+    /// The [`Resolution`] for the snapshot keyed by `id` (already parsed as `dep_path`), wherever
+    /// this lockfile version stores it: directly on the snapshot for lockfileVersion 5/6/7, or in
+    /// the separate `packages` section (keyed by bare `name@version`) for lockfileVersion 9.
+    pub fn resolution(&self, dep_path: &DepPath, id: &str) -> Option<&Resolution> {
+        match self {
+            Lockfile::V9 { packages, .. } => packages
+                .get(&format!("{}@{}", dep_path.name, dep_path.version))
+                .and_then(|package| package.resolution.as_ref()),
+            Lockfile::V5 { .. } | Lockfile::V6 { .. } | Lockfile::V7 { .. } => self
+                .snapshots()
+                .get(id)
+                .and_then(|snapshot| snapshot.resolution.as_ref()),
+        }
+    }
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -155,55 +484,293 @@ pub struct Snapshot {
     /// The package names of peer dependencies of the transitive package dependencies,
     /// excluding direct peer dependencies.
     pub transitive_peer_dependencies: Vec<String>,
+
+    #[serde(default)]
+    /// How pnpm resolved this package, including its integrity hash.
+    ///
+    /// Only populated for lockfileVersion 5/6/7, whose single `packages`/`snapshots` section
+    /// combines resolution and the dependency tree. For lockfileVersion 9, resolution instead
+    /// lives on the separate [`PackageMeta`] entry; use [`Lockfile::resolution`] rather than this
+    /// field directly to support all versions.
+    pub resolution: Option<Resolution>,
 }
 
-/// Performs the `pnpm tree {name}` CLI command, printing a user-friendly inverse dependency tree
-/// to stdout of the specified package name for the pnpm workspace in the current directory.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// Per-package resolution metadata from lockfileVersion 9's separate `packages` section, keyed by
+/// bare `name@version` rather than the full peer-qualified depPath. See [`Lockfile::V9`].
+pub struct PackageMeta {
+    #[serde(default)]
+    /// How pnpm resolved this package, including its integrity hash.
+    pub resolution: Option<Resolution>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// How pnpm resolved a package to a concrete artifact, used by both [`Snapshot`] (lockfileVersion
+/// 5/6/7) and [`PackageMeta`] (lockfileVersion 9).
+pub struct Resolution {
+    #[serde(default)]
+    /// The Subresource Integrity hash of the package tarball, e.g. "sha512-...".
+    ///
+    /// Absent for packages resolved some other way, e.g. git dependencies.
+    pub integrity: Option<String>,
+
+    #[serde(default)]
+    /// The URL the package tarball was fetched from, if resolved from a registry or URL.
+    pub tarball: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The output format for [`print_tree_to`].
+pub enum Format {
+    /// Indented plain text, one node per line (the default). Unstable, may change at any time.
+    Text,
+    /// A nested JSON structure of `{ id, kind, version, children/dependents }` objects, with a
+    /// `"truncated": true` marker in place of `children`/`dependents` for already-visited nodes.
+    Json,
+    /// A Graphviz `digraph` of the relevant subgraph, suitable for piping into `dot`.
+    Dot,
+}
+
+/// Performs the `pnpm-extra tree {name}` CLI command, printing a user-friendly dependency tree to
+/// stdout of the specified package name for the pnpm workspace in `workspace_dir`.
+///
+/// If `invert` is set (the default, as this was originally modelled on `pnpm why`/`cargo tree
+/// -i`), the tree walks dependents of `name`, i.e. "what depends on this"; otherwise it walks
+/// dependencies, i.e. "what does this depend on".
 ///
-/// The output format is not specified and may change without a breaking change.
+/// The text output format is not specified and may change without a breaking change; use
+/// [`print_tree_to`] with [`Format::Json`] or [`Format::Dot`] for a stable, machine-readable
+/// format.
+///
+/// # Errors
+/// - [`Error::ReadLockfile`] If the pnpm-lock.yaml file cannot be read.
+/// - [`Error::ParseLockfile`] If the pnpm-lock.yaml file cannot be parsed.
+/// - [`Error::InvalidDepPath`] If a snapshot key could not be parsed.
+pub fn print_tree(workspace_dir: &Path, name: &str, invert: bool) -> Result<()> {
+    print_tree_to(&mut std::io::stdout(), Format::Text, workspace_dir, name, invert)
+}
+
+/// As [`print_tree`], but writing in the given [`Format`] to an arbitrary writer instead of
+/// stdout-as-text, for downstream tools that want to consume the graph programmatically.
 ///
 /// # Errors
 /// - [`Error::ReadLockfile`] If the pnpm-lock.yaml file cannot be read.
 /// - [`Error::ParseLockfile`] If the pnpm-lock.yaml file cannot be parsed.
-/// - [`Error::UnexpectedLockfileContent`] If the lockfile content could not otherwise be
-///   understood.
-pub fn print_tree(workspace_dir: &Path, name: &str) -> Result<()> {
+/// - [`Error::InvalidDepPath`] If a snapshot key could not be parsed.
+/// - [`Error::WriteOutput`] If writing to `writer` fails.
+pub fn print_tree_to(
+    writer: &mut dyn std::io::Write,
+    format: Format,
+    workspace_dir: &Path,
+    name: &str,
+    invert: bool,
+) -> Result<()> {
     let lockfile = Lockfile::read_from_workspace_dir(workspace_dir)?;
 
     let graph = DependencyGraph::from_lockfile(&lockfile, workspace_dir)?;
+    let edges = if invert { &graph.inverse } else { &graph.forward };
 
-    // Print the tree, skipping repeated nodes.
-    let mut seen = HashSet::<NodeId>::new();
+    // `edges` alone misses leaf nodes on the side with no outgoing entries (e.g. a package with
+    // no dependencies has no `forward` entry), so match against both maps' keys.
+    let roots: BTreeSet<&NodeId> = graph
+        .forward
+        .keys()
+        .chain(graph.inverse.keys())
+        .filter(|node_id| matches!(node_id, NodeId::Package { name: package_name, .. } if name == package_name))
+        .collect();
+    let roots: Vec<&NodeId> = roots.into_iter().collect();
+
+    match format {
+        Format::Text => print_tree_text(writer, edges, &roots),
+        Format::Json => print_tree_json(writer, edges, &roots, invert),
+        Format::Dot => print_tree_dot(writer, edges, &roots),
+    }
+    .map_err(Error::WriteOutput)
+}
 
+/// Write `roots` and their transitive `edges` as indented plain text, skipping repeated nodes.
+fn print_tree_text(
+    writer: &mut dyn std::io::Write,
+    edges: &HashMap<NodeId, HashSet<NodeId>>,
+    roots: &[&NodeId],
+) -> std::io::Result<()> {
     fn print_tree_inner(
-        inverse_deps: &DependencyGraph,
+        writer: &mut dyn std::io::Write,
+        edges: &HashMap<NodeId, HashSet<NodeId>>,
         seen: &mut HashSet<NodeId>,
         node_id: &NodeId,
         depth: usize,
-    ) {
+    ) -> std::io::Result<()> {
         if !seen.insert(node_id.clone()) {
-            println!("{:indent$}{node_id} (*)", "", indent = depth * 2,);
-            return;
+            writeln!(writer, "{:indent$}{node_id} (*)", "", indent = depth * 2)?;
+            return Ok(());
         }
-        let Some(dep_ids) = inverse_deps.inverse.get(node_id) else {
-            println!("{:indent$}{node_id}", "", indent = depth * 2,);
-            return;
+        let Some(dep_ids) = edges.get(node_id) else {
+            writeln!(writer, "{:indent$}{node_id}", "", indent = depth * 2)?;
+            return Ok(());
         };
-        println!("{:indent$}{node_id}:", "", indent = depth * 2,);
+        writeln!(writer, "{:indent$}{node_id}:", "", indent = depth * 2)?;
         for dep_id in dep_ids {
-            print_tree_inner(inverse_deps, seen, dep_id, depth + 1);
+            print_tree_inner(writer, edges, seen, dep_id, depth + 1)?;
         }
+        Ok(())
     }
 
-    for node_id in graph.inverse.keys() {
-        if matches!(node_id, NodeId::Package { name: package_name, .. } if name == package_name) {
-            print_tree_inner(&graph, &mut seen, node_id, 0);
+    let mut seen = HashSet::<NodeId>::new();
+    for node_id in roots {
+        print_tree_inner(writer, edges, &mut seen, node_id, 0)?;
+    }
+    Ok(())
+}
+
+/// Write `roots` and their transitive `edges` as a JSON array of nested
+/// `{ id, kind, version, children/dependents }` objects, with a `"truncated": true` marker in
+/// place of the children list for already-visited nodes so cycles stay finite.
+fn print_tree_json(
+    writer: &mut dyn std::io::Write,
+    edges: &HashMap<NodeId, HashSet<NodeId>>,
+    roots: &[&NodeId],
+    invert: bool,
+) -> std::io::Result<()> {
+    let children_key = if invert { "dependents" } else { "children" };
+
+    fn node_to_json(
+        edges: &HashMap<NodeId, HashSet<NodeId>>,
+        seen: &mut HashSet<NodeId>,
+        node_id: &NodeId,
+        children_key: &str,
+    ) -> serde_json::Value {
+        let mut object = serde_json::Map::new();
+        match node_id {
+            NodeId::Importer { path } => {
+                object.insert("id".to_string(), path.display().to_string().into());
+                object.insert("kind".to_string(), "importer".into());
+            }
+            NodeId::Package { name, version } => {
+                object.insert("id".to_string(), format!("{name}@{version}").into());
+                object.insert("kind".to_string(), "package".into());
+                object.insert("version".to_string(), version.clone().into());
+            }
+        }
+        if !seen.insert(node_id.clone()) {
+            object.insert("truncated".to_string(), true.into());
+            return serde_json::Value::Object(object);
+        }
+        let children = edges.get(node_id).into_iter().flatten().map(|dep_id| {
+            node_to_json(edges, seen, dep_id, children_key)
+        });
+        object.insert(children_key.to_string(), children.collect());
+        serde_json::Value::Object(object)
+    }
+
+    let mut seen = HashSet::<NodeId>::new();
+    let tree: serde_json::Value = roots
+        .iter()
+        .map(|node_id| node_to_json(edges, &mut seen, node_id, children_key))
+        .collect();
+    serde_json::to_writer_pretty(&mut *writer, &tree)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+    writeln!(writer)
+}
+
+/// Write the subgraph reachable from `roots` over `edges` as a Graphviz digraph.
+fn print_tree_dot(
+    writer: &mut dyn std::io::Write,
+    edges: &HashMap<NodeId, HashSet<NodeId>>,
+    roots: &[&NodeId],
+) -> std::io::Result<()> {
+    writeln!(writer, "digraph {{")?;
+
+    let mut seen = HashSet::<NodeId>::new();
+    let mut stack: Vec<NodeId> = roots.iter().map(|node_id| (*node_id).clone()).collect();
+    while let Some(node_id) = stack.pop() {
+        if !seen.insert(node_id.clone()) {
+            continue;
+        }
+        // Write a bare node statement up front, so nodes with no outgoing edges (a leaf, or a
+        // root nothing depends on) still appear in the output rather than being silently dropped.
+        writeln!(writer, "  {:?};", node_id.to_string())?;
+        for dep_id in edges.get(&node_id).into_iter().flatten() {
+            writeln!(writer, "  {:?} -> {:?};", node_id.to_string(), dep_id.to_string())?;
+            stack.push(dep_id.clone());
+        }
+    }
+
+    writeln!(writer, "}}")
+}
+
+/// Performs the `pnpm-extra tree --duplicates` CLI command (modelled on `cargo tree -d`),
+/// printing every package name resolved to more than one distinct version, each version
+/// alongside the importers that pull it in (transitively).
+///
+/// # Errors
+/// - [`Error::ReadLockfile`] If the pnpm-lock.yaml file cannot be read.
+/// - [`Error::ParseLockfile`] If the pnpm-lock.yaml file cannot be parsed.
+/// - [`Error::InvalidDepPath`] If a snapshot key could not be parsed.
+pub fn print_duplicates(workspace_dir: &Path) -> Result<()> {
+    let lockfile = Lockfile::read_from_workspace_dir(workspace_dir)?;
+    let graph = DependencyGraph::from_lockfile(&lockfile, workspace_dir)?;
+
+    let mut versions_by_name = BTreeMap::<&str, BTreeSet<&str>>::new();
+    for node_id in graph.forward.keys().chain(graph.inverse.keys()) {
+        if let NodeId::Package { name, version } = node_id {
+            versions_by_name.entry(name).or_default().insert(version);
+        }
+    }
+
+    for (name, versions) in &versions_by_name {
+        if versions.len() < 2 {
+            continue;
+        }
+        println!("{name}");
+        for version in versions {
+            let node_id = NodeId::Package {
+                name: (*name).to_string(),
+                version: (*version).to_string(),
+            };
+            let importers = importing_paths(&graph, &node_id);
+            if importers.is_empty() {
+                println!("  {version}");
+            } else {
+                let importers = importers
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("  {version} ({importers})");
+            }
         }
     }
 
     Ok(())
 }
 
+/// The set of importer paths that transitively depend on `node_id`, found by following
+/// [`DependencyGraph::inverse`] edges up from it until reaching [`NodeId::Importer`] nodes.
+fn importing_paths(graph: &DependencyGraph, node_id: &NodeId) -> BTreeSet<PathBuf> {
+    let mut seen = HashSet::new();
+    let mut importers = BTreeSet::new();
+    let mut stack = vec![node_id.clone()];
+    while let Some(node_id) = stack.pop() {
+        if !seen.insert(node_id.clone()) {
+            continue;
+        }
+        match node_id {
+            NodeId::Importer { path } => {
+                importers.insert(path);
+            }
+            NodeId::Package { .. } => {
+                if let Some(dependents) = graph.inverse.get(&node_id) {
+                    stack.extend(dependents.iter().cloned());
+                }
+            }
+        }
+    }
+    importers
+}
+
 #[derive(Default)]
 /// A dependency graph.
 pub struct DependencyGraph {
@@ -221,12 +788,11 @@ impl DependencyGraph {
     /// and filter the dependency tree.
     ///
     /// # Errors
-    /// - [`Error::UnexpectedLockfileContent`] If the lockfile content could not be understood.
+    /// - [`Error::InvalidDepPath`] If a snapshot key could not be parsed.
     pub fn from_lockfile(lockfile: &Lockfile, workspace_dir: &Path) -> Result<Self> {
-        let Lockfile::V9 {
-            importers,
-            snapshots,
-        } = lockfile;
+        let importers = lockfile.importers();
+        let snapshots = lockfile.snapshots();
+        let parse_dep_path = lockfile.dep_path_parser();
 
         let mut forward = HashMap::<NodeId, HashSet<NodeId>>::new();
         let mut inverse = HashMap::<NodeId, HashSet<NodeId>>::new();
@@ -258,10 +824,12 @@ impl DependencyGraph {
         }
 
         for (id, entry) in snapshots {
-            let split = 1 + id[1..].find('@').ok_or(Error::UnexpectedLockfileContent)?;
+            let dep_path = parse_dep_path(id)?;
             let node_id = NodeId::Package {
-                name: id[..split].to_string(),
-                version: id[split + 1..].to_string(),
+                // Keep the full qualified version (including any peer/patch suffix) so this
+                // matches the version strings recorded against the importers' own dependencies.
+                version: dep_path.qualified_version(),
+                name: dep_path.name,
             };
             for (dep_name, dep_version) in &entry.dependencies {
                 let dep_id = NodeId::Package {
@@ -307,3 +875,188 @@ impl std::fmt::Display for NodeId {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DepPath, Lockfile};
+
+    #[test]
+    fn parse_simple() {
+        let dep_path = DepPath::parse("foo@1.2.3").unwrap();
+        assert_eq!(dep_path.name, "foo");
+        assert_eq!(dep_path.version, "1.2.3");
+        assert_eq!(dep_path.peer_suffix, None);
+        assert_eq!(dep_path.patch, None);
+    }
+
+    #[test]
+    fn parse_scoped() {
+        let dep_path = DepPath::parse("@scope/foo@1.2.3").unwrap();
+        assert_eq!(dep_path.name, "@scope/foo");
+        assert_eq!(dep_path.version, "1.2.3");
+    }
+
+    #[test]
+    fn parse_multiple_peers() {
+        let dep_path = DepPath::parse("bar@4.5.6(react@18.0.0)(zod@3.0.0)").unwrap();
+        assert_eq!(dep_path.name, "bar");
+        assert_eq!(dep_path.version, "4.5.6");
+        assert_eq!(dep_path.peer_suffix.as_deref(), Some("(react@18.0.0)(zod@3.0.0)"));
+        assert_eq!(dep_path.patch, None);
+    }
+
+    #[test]
+    fn parse_patch() {
+        let dep_path = DepPath::parse("foo@1.2.3(patch_hash=abc)").unwrap();
+        assert_eq!(dep_path.version, "1.2.3");
+        assert_eq!(dep_path.peer_suffix, None);
+        assert_eq!(dep_path.patch.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn parse_peer_and_patch() {
+        let dep_path = DepPath::parse("foo@1.2.3(react@18.0.0)(patch_hash=abc)").unwrap();
+        assert_eq!(dep_path.peer_suffix.as_deref(), Some("(react@18.0.0)"));
+        assert_eq!(dep_path.patch.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn parse_nested_parens_in_peer() {
+        // The "@" inside a peer group must never be mistaken for the name/version separator.
+        let dep_path = DepPath::parse("foo@1.2.3(bar@4.5.6(patch_hash=abc))").unwrap();
+        assert_eq!(dep_path.version, "1.2.3");
+        assert_eq!(dep_path.peer_suffix.as_deref(), Some("(bar@4.5.6(patch_hash=abc))"));
+    }
+
+    #[test]
+    fn parse_no_at_is_invalid() {
+        assert!(DepPath::parse("foo-1.2.3").is_err());
+    }
+
+    #[test]
+    fn parse_unbalanced_parens_is_invalid() {
+        assert!(DepPath::parse("foo@1.2.3(react@18.0.0").is_err());
+    }
+
+    #[test]
+    fn parse_slashed_simple() {
+        let dep_path = DepPath::parse_slashed("/foo/1.2.3").unwrap();
+        assert_eq!(dep_path.name, "foo");
+        assert_eq!(dep_path.version, "1.2.3");
+        assert_eq!(dep_path.patch, None);
+    }
+
+    #[test]
+    fn parse_slashed_scoped() {
+        let dep_path = DepPath::parse_slashed("/@scope/foo/1.2.3").unwrap();
+        assert_eq!(dep_path.name, "@scope/foo");
+        assert_eq!(dep_path.version, "1.2.3");
+    }
+
+    #[test]
+    fn parse_slashed_peer() {
+        let dep_path = DepPath::parse_slashed("/bar/4.5.6_peer@7.8.9").unwrap();
+        assert_eq!(dep_path.version, "4.5.6");
+        assert_eq!(dep_path.peer_suffix.as_deref(), Some("_peer@7.8.9"));
+    }
+
+    #[test]
+    fn parse_v6_simple() {
+        let dep_path = DepPath::parse_v6("/lodash@4.17.21").unwrap();
+        assert_eq!(dep_path.name, "lodash");
+        assert_eq!(dep_path.version, "4.17.21");
+    }
+
+    #[test]
+    fn parse_v6_scoped_with_peer() {
+        let dep_path =
+            DepPath::parse_v6("/@babel/code-frame@7.24.7(supports-color@5.5.0)").unwrap();
+        assert_eq!(dep_path.name, "@babel/code-frame");
+        assert_eq!(dep_path.version, "7.24.7");
+        assert_eq!(dep_path.peer_suffix.as_deref(), Some("(supports-color@5.5.0)"));
+    }
+
+    #[test]
+    fn parse_v6_missing_slash_is_invalid() {
+        assert!(DepPath::parse_v6("lodash@4.17.21").is_err());
+    }
+
+    #[test]
+    fn deserialize_v5_reads_packages_as_snapshots() {
+        let lockfile: Lockfile = serde_yaml::from_str(
+            "
+            lockfileVersion: '5.4'
+            importers: {}
+            packages:
+              /foo/1.2.3:
+                resolution: {integrity: sha512-abc}
+            ",
+        )
+        .unwrap();
+        let Lockfile::V5 { snapshots, .. } = &lockfile else {
+            panic!("expected V5, got {lockfile:?}");
+        };
+        assert!(snapshots.contains_key("/foo/1.2.3"));
+    }
+
+    #[test]
+    fn deserialize_v7_reads_snapshots() {
+        let lockfile: Lockfile = serde_yaml::from_str(
+            "
+            lockfileVersion: 7
+            importers: {}
+            snapshots:
+              foo@1.2.3: {}
+            ",
+        )
+        .unwrap();
+        let Lockfile::V7 { snapshots, .. } = &lockfile else {
+            panic!("expected V7, got {lockfile:?}");
+        };
+        assert!(snapshots.contains_key("foo@1.2.3"));
+    }
+
+    #[test]
+    fn deserialize_v9_keeps_packages_and_snapshots_separate() {
+        // Real lockfileVersion 9 files have both sections at once, with different meanings: this
+        // is the case that broke when `snapshots` was aliased to `packages` in `Raw`.
+        let lockfile: Lockfile = serde_yaml::from_str(
+            "
+            lockfileVersion: '9.0'
+            importers: {}
+            packages:
+              foo@1.2.3:
+                resolution: {integrity: sha512-abc}
+            snapshots:
+              foo@1.2.3:
+                dependencies:
+                  bar: 4.5.6
+            ",
+        )
+        .unwrap();
+        let Lockfile::V9 { packages, snapshots, .. } = &lockfile else {
+            panic!("expected V9, got {lockfile:?}");
+        };
+        assert!(packages.contains_key("foo@1.2.3"));
+        assert!(snapshots.contains_key("foo@1.2.3"));
+        assert_eq!(
+            snapshots["foo@1.2.3"].dependencies.get("bar").map(String::as_str),
+            Some("4.5.6")
+        );
+
+        let dep_path = DepPath::parse("foo@1.2.3").unwrap();
+        let resolution = lockfile.resolution(&dep_path, "foo@1.2.3").unwrap();
+        assert_eq!(resolution.integrity.as_deref(), Some("sha512-abc"));
+    }
+
+    #[test]
+    fn deserialize_unsupported_version_is_invalid() {
+        let result: Result<Lockfile, _> = serde_yaml::from_str(
+            "
+            lockfileVersion: '1.0'
+            importers: {}
+            ",
+        );
+        assert!(result.is_err());
+    }
+}
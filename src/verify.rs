@@ -0,0 +1,157 @@
+//! Lockfile integrity auditing and hash fixup (`pnpm-extra verify`).
+
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context as _, Result};
+
+use crate::tree::{DepPath, Lockfile};
+use crate::yaml_lines::{block_end, child_indent, find_key, find_or_insert_key};
+
+/// Performs the `pnpm-extra verify` CLI command.
+///
+/// Walks the lockfile's snapshots, printing every one whose `resolution.integrity` is missing or
+/// doesn't look like a valid SRI hash. If `fix` is set, missing/malformed integrity values are
+/// instead resolved by querying `pnpm view --json <name>@<version>` (as `catalog add` already
+/// does for versions) and the corrected hashes are written back into `pnpm-lock.yaml`.
+///
+/// # Errors
+/// - If the lockfile cannot be read or parsed.
+/// - If a snapshot's depPath cannot be parsed.
+/// - If fixing: if `pnpm view` cannot be run or its output cannot be parsed, or if the lockfile
+///   cannot be written back.
+pub fn verify(workspace_dir: &Path, fix: bool) -> Result<()> {
+    let lockfile = Lockfile::read_from_workspace_dir(workspace_dir)?;
+    let parse_dep_path = lockfile.dep_path_parser();
+
+    let mut fixes = Vec::new();
+    let mut all_ok = true;
+    for id in lockfile.snapshots().keys() {
+        let dep_path = parse_dep_path(id).with_context(|| format!("parsing depPath {id}"))?;
+        let integrity = lockfile
+            .resolution(&dep_path, id)
+            .and_then(|resolution| resolution.integrity.as_deref());
+        if is_valid_integrity(integrity) {
+            continue;
+        }
+        all_ok = false;
+
+        if !fix {
+            println!("{id}: missing or malformed integrity ({integrity:?})");
+            continue;
+        }
+
+        println!("resolving integrity for {id}");
+        let integrity = resolve_integrity(&dep_path.name, &dep_path.version)
+            .with_context(|| format!("resolving integrity for {id}"))?;
+        println!("found {id}: {integrity}");
+        fixes.push((id.clone(), dep_path, integrity));
+    }
+
+    if fix {
+        if fixes.is_empty() {
+            println!("all snapshots already have integrity hashes");
+        } else {
+            write_fixes(workspace_dir, &lockfile, &fixes)?;
+        }
+    } else if all_ok {
+        println!("all snapshots have integrity hashes");
+    }
+
+    Ok(())
+}
+
+/// Whether `integrity` looks like a valid SRI hash, e.g. "sha512-<base64>".
+fn is_valid_integrity(integrity: Option<&str>) -> bool {
+    let Some((algorithm, hash)) = integrity.and_then(|integrity| integrity.split_once('-')) else {
+        return false;
+    };
+    matches!(algorithm, "sha1" | "sha256" | "sha512") && !hash.is_empty()
+}
+
+/// Resolve the current registry integrity hash for `name@version` via `pnpm view`.
+fn resolve_integrity(name: &str, version: &str) -> Result<String> {
+    let output = Command::new("pnpm")
+        .arg("view")
+        .arg("--json")
+        .arg(format!("{name}@{version}"))
+        .output()
+        .context("running pnpm view")?;
+    let pkg = serde_json::from_slice::<serde_json::Value>(&output.stdout)
+        .context("reading pnpm view output json")?;
+    pkg["dist"]["integrity"]
+        .as_str()
+        .map(str::to_string)
+        .context("integrity not found in pnpm view output")
+}
+
+/// Write resolved `(depPath, dep_path, integrity)` fixes back into `pnpm-lock.yaml`, preserving
+/// everything else in the file.
+///
+/// For lockfileVersion 9, resolution lives in the separate `packages` section keyed by bare
+/// `name@version`, not on the `snapshots` entry the depPath was read from; for older versions
+/// the depPath itself is the key to fix, in whichever of `snapshots`/`packages` exists. See
+/// [`Lockfile::resolution`].
+fn write_fixes(
+    workspace_dir: &Path,
+    lockfile: &Lockfile,
+    fixes: &[(String, DepPath, String)],
+) -> Result<()> {
+    let path = workspace_dir.join("pnpm-lock.yaml");
+    let data = std::fs::read_to_string(&path).context("reading pnpm-lock.yaml")?;
+    let mut lines: Vec<String> = data.lines().map(str::to_string).collect();
+
+    for (id, dep_path, integrity) in fixes {
+        let len = lines.len();
+        let (section_key, entry_key) = match lockfile {
+            Lockfile::V9 { .. } => ("packages", format!("{}@{}", dep_path.name, dep_path.version)),
+            Lockfile::V5 { .. } | Lockfile::V6 { .. } | Lockfile::V7 { .. } => {
+                let section_key = ["snapshots", "packages"]
+                    .into_iter()
+                    .find(|key| find_key(&lines, 0, 0, len, key).is_some())
+                    .context("no snapshots/packages section found in pnpm-lock.yaml")?;
+                (section_key, id.clone())
+            }
+        };
+        let Some(section_line) = find_key(&lines, 0, 0, len, section_key) else {
+            // The section must have been removed from the lockfile since it was read; skip it.
+            continue;
+        };
+        let section_end = block_end(&lines, 0, section_line, len);
+        let entry_indent = child_indent(&lines, section_line, section_end, 2);
+
+        let Some(entry_line) =
+            find_key(&lines, entry_indent, section_line + 1, section_end, &entry_key)
+        else {
+            // The entry must have been removed from the lockfile since it was read; skip it.
+            continue;
+        };
+        let entry_end = block_end(&lines, entry_indent, entry_line, section_end);
+        let resolution_indent = entry_indent + 2;
+        let (resolution_line, resolution_end) = find_or_insert_key(
+            &mut lines,
+            resolution_indent,
+            entry_line + 1,
+            entry_end,
+            "resolution",
+        );
+        let integrity_indent =
+            child_indent(&lines, resolution_line, resolution_end, resolution_indent + 2);
+
+        let entry = format!("{}integrity: {integrity}", " ".repeat(integrity_indent));
+        match find_key(
+            &lines,
+            integrity_indent,
+            resolution_line + 1,
+            resolution_end,
+            "integrity",
+        ) {
+            Some(integrity_line) => lines[integrity_line] = entry,
+            None => lines.insert(resolution_end, entry),
+        }
+    }
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    std::fs::write(&path, output).context("writing pnpm-lock.yaml")
+}
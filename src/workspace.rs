@@ -0,0 +1,65 @@
+//! Reading and format-preserving editing of `pnpm-workspace.yaml`.
+
+use anyhow::{bail, Context as _, Result};
+
+use crate::yaml_lines::{child_indent, find_or_insert_key, yaml_key};
+
+/// Parse and return the content of pnpm-workspace.yaml as a serde_yaml::Mapping.
+///
+/// This is unlikely to be useful as this is a human edited file and serde_yaml does not preserve
+/// comments or formatting, but is provided for completeness.
+///
+/// # Errors
+/// - If the pnpm-workspace.yaml file cannot be read or parsed.
+pub fn read_workspace() -> Result<serde_yaml::Mapping> {
+    let data = std::fs::read("pnpm-workspace.yaml").context("reading pnpm-workspace.yaml");
+    let workspace = serde_yaml::from_slice::<serde_yaml::Value>(&data?)
+        .context("parsing pnpm-workspace.yaml")?;
+    let workspace = match workspace {
+        serde_yaml::Value::Mapping(map) => map,
+        _ => bail!("pnpm-workspace.yaml content is not a mapping?"),
+    };
+    Ok(workspace)
+}
+
+/// Add a `name: specifier` entry to a catalog in pnpm-workspace.yaml, editing the file's text in
+/// place rather than rewriting the whole document from a parsed `serde_yaml::Mapping`.
+///
+/// `catalog` selects the `catalogs.<catalog>` table, or the top-level `catalog` table if `None`.
+/// The targeted table is created if it doesn't already exist yet.
+///
+/// This only touches the lines of that table, leaving comments, blank lines, key ordering, and
+/// quoting style everywhere else in the file untouched - analogous to how `toml_edit` preserves
+/// TOML document structure. This also means it never needs to shell out to a formatter
+/// afterwards.
+///
+/// # Errors
+/// - If the pnpm-workspace.yaml file cannot be read or written.
+pub fn edit_workspace(catalog: Option<&str>, name: &str, specifier: &str) -> Result<()> {
+    let data =
+        std::fs::read_to_string("pnpm-workspace.yaml").context("reading pnpm-workspace.yaml")?;
+    let mut lines: Vec<String> = data.lines().map(str::to_string).collect();
+    let len = lines.len();
+
+    let (key_line, key_indent, end) = match catalog {
+        None => {
+            let (key_line, end) = find_or_insert_key(&mut lines, 0, 0, len, "catalog");
+            (key_line, 0, end)
+        }
+        Some(catalog) => {
+            let (catalogs_line, catalogs_end) = find_or_insert_key(&mut lines, 0, 0, len, "catalogs");
+            let indent = child_indent(&lines, catalogs_line, catalogs_end, 2);
+            let (key_line, end) =
+                find_or_insert_key(&mut lines, indent, catalogs_line + 1, catalogs_end, catalog);
+            (key_line, indent, end)
+        }
+    };
+
+    let entry_indent = child_indent(&lines, key_line, end, key_indent + 2);
+    let entry = format!("{}{}: {specifier}", " ".repeat(entry_indent), yaml_key(name));
+    lines.insert(end, entry);
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    std::fs::write("pnpm-workspace.yaml", output).context("writing pnpm-workspace.yaml")
+}
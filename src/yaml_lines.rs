@@ -0,0 +1,89 @@
+//! Helpers for locating and editing blocks of a block-style YAML document by line, without fully
+//! parsing or re-serializing it. Used to make targeted, format-preserving edits to
+//! `pnpm-workspace.yaml` and `pnpm-lock.yaml`.
+
+/// The number of leading ASCII space characters on `line`.
+pub(crate) fn leading_spaces(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Whether `line` is blank or a comment, and so ignored when looking for block boundaries.
+pub(crate) fn is_blank_or_comment(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+/// Whether `key` must be quoted to be used as a YAML plain scalar, e.g. because it starts with a
+/// reserved indicator character. Scoped package/catalog names (`@scope/pkg`) are the case that
+/// matters here: `@` is a YAML plain-scalar indicator, so pnpm and hand-authored workspace files
+/// always single-quote those keys.
+fn needs_quoting(key: &str) -> bool {
+    matches!(
+        key.chars().next(),
+        Some('@' | '*' | '&' | '!' | '|' | '>' | '%' | '`' | '"' | '\'' | '{' | '[' | ',' | '#')
+    )
+}
+
+/// Format `key` for use as a YAML mapping key, single-quoting it (and doubling any embedded
+/// single quotes, per YAML's quoting rules) if [`needs_quoting`].
+pub(crate) fn yaml_key(key: &str) -> String {
+    if needs_quoting(key) {
+        format!("'{}'", key.replace('\'', "''"))
+    } else {
+        key.to_string()
+    }
+}
+
+/// Find a `key:` line (quoted per [`yaml_key`] if necessary) at `indent` within
+/// `lines[start..end]`.
+pub(crate) fn find_key(
+    lines: &[String],
+    indent: usize,
+    start: usize,
+    end: usize,
+    key: &str,
+) -> Option<usize> {
+    let prefix = format!("{}{key}:", " ".repeat(indent));
+    let quoted_prefix = format!("{}{}:", " ".repeat(indent), yaml_key(key));
+    lines[start..end]
+        .iter()
+        .position(|line| line.starts_with(&prefix) || line.starts_with(&quoted_prefix))
+        .map(|offset| start + offset)
+}
+
+/// The end (exclusive) of the block owned by the key at `lines[key_line]`: the first line within
+/// `(key_line, end)` that is neither blank/a comment nor indented deeper than `indent`.
+pub(crate) fn block_end(lines: &[String], indent: usize, key_line: usize, end: usize) -> usize {
+    lines[key_line + 1..end]
+        .iter()
+        .position(|line| !is_blank_or_comment(line) && leading_spaces(line) <= indent)
+        .map_or(end, |offset| key_line + 1 + offset)
+}
+
+/// The indentation already used by entries inside `lines[key_line + 1..end]`, or `default` if
+/// the block is empty.
+pub(crate) fn child_indent(lines: &[String], key_line: usize, end: usize, default: usize) -> usize {
+    lines[key_line + 1..end]
+        .iter()
+        .find(|line| !is_blank_or_comment(line))
+        .map_or(default, |line| leading_spaces(line))
+}
+
+/// Like [`find_key`], but appends an empty `key:` line at `end` if not found.
+///
+/// Returns `(key_line, block_end)` for the (possibly newly inserted) key.
+pub(crate) fn find_or_insert_key(
+    lines: &mut Vec<String>,
+    indent: usize,
+    start: usize,
+    end: usize,
+    key: &str,
+) -> (usize, usize) {
+    match find_key(lines, indent, start, end, key) {
+        Some(key_line) => (key_line, block_end(lines, indent, key_line, end)),
+        None => {
+            lines.insert(end, format!("{}{}:", " ".repeat(indent), yaml_key(key)));
+            (end, end + 1)
+        }
+    }
+}